@@ -1,12 +1,31 @@
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit, Nonce};
+use chacha20poly1305::aead::Aead;
 use clap::{Parser, Subcommand, ValueEnum};
 use clap::CommandFactory;
+use pbkdf2::pbkdf2_hmac;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 use std::string::String;
 use solana_clap_v3_utils::input_validators::normalize_to_url_if_moniker;
 use solana_client::rpc_client::RpcClient;
-use solana_sdk::signature::{Keypair, read_keypair};
+use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_remote_wallet::locator::Locator as RemoteWalletLocator;
+use solana_remote_wallet::remote_keypair::generate_remote_keypair;
+use solana_remote_wallet::remote_wallet::{maybe_wallet_manager, RemoteWalletManager};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::derivation_path::DerivationPath;
+use solana_sdk::hash::Hash;
+use solana_sdk::native_token::sol_to_lamports;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, read_keypair};
+use solana_sdk::signer::Signer;
 use solana_sdk::transaction::Transaction;
 
 #[derive(Parser)]
@@ -23,12 +42,21 @@ enum Commands {
         /// Format to transform for keypair
         #[arg(long, short, value_enum)]
         transform: Format,
-        /// Filepath to *.json file contain keypair
-        #[arg(short, long, value_name = "KEYPAIR")]
-        path: Option<PathBuf>,
-        /// Raw value from *.json file representation keypair
-        #[arg(short, long, value_name = "RAW_VALUE", required_unless_present = "path")]
+        /// Signer URI contain keypair: file:///path or prompt:// (hardware wallets have no exportable bytes)
+        #[arg(short, long, value_name = "SIGNER_URI")]
+        path: Option<String>,
+        /// Raw value from *.json file representation keypair (omit when --transform mnemonic)
+        #[arg(short, long, value_name = "RAW_VALUE")]
         value: Option<String>,
+        /// Read --path as a passphrase-encrypted keypair file and print the result encrypted under a new passphrase (prompts for the passphrases)
+        #[arg(long)]
+        encrypted: bool,
+        /// Optional BIP39 passphrase used when deriving from or generating a mnemonic
+        #[arg(long, value_name = "PASSPHRASE", default_value = "")]
+        mnemonic_passphrase: String,
+        /// Format of the input value/file (defaults to the inverse of --transform)
+        #[arg(long, value_enum)]
+        format: Option<Format>,
     },
     #[clap(arg_required_else_help = true)]
     TransactionSend {
@@ -38,11 +66,47 @@ enum Commands {
         /// Raw base64 encoded transaction
         #[arg(short, long)]
         transaction: String,
-        /// Filepath to *.json file contain keypair for sign transaction
-        #[arg(short, long, value_name = "KEYPAIR")]
-        signer: Option<PathBuf>,
+        /// Signer URI: file:///path, usb://ledger?key=0/0, or prompt:// (repeatable, deduped by pubkey)
+        #[arg(short, long, value_name = "SIGNER_URI")]
+        signer: Vec<String>,
         #[arg(long, short, value_enum)]
-        format: Option<Format>
+        format: Option<Format>,
+        /// Treat file:// signer URIs as passphrase-encrypted keypair files (prompts for the passphrase)
+        #[arg(long)]
+        encrypted: bool,
+        /// Partial-sign locally and print signatures instead of broadcasting the transaction
+        #[arg(long)]
+        sign_only: bool,
+        /// Signature collected from another offline signer, as PUBKEY=BASE58_SIGNATURE (repeatable)
+        #[arg(long = "presigner", value_name = "PUBKEY=BASE58_SIGNATURE")]
+        presigners: Vec<String>,
+        /// Commitment level to confirm against
+        #[arg(long, value_enum, default_value = "confirmed")]
+        commitment: CommitmentLevelArg,
+        /// Skip the local preflight transaction simulation
+        #[arg(long)]
+        skip_preflight: bool,
+        /// Maximum number of times the RPC node should retry broadcasting the transaction
+        #[arg(long)]
+        max_retries: Option<usize>,
+    },
+    #[clap(arg_required_else_help = true)]
+    Airdrop {
+        /// URL for Solana's JSON RPC or moniker (or their first letter): [mainnet-beta, testnet, devnet, localhost]
+        #[arg(short, long)]
+        url: String,
+        /// Pubkey to fund (derived from --signer when omitted)
+        #[arg(long, value_name = "PUBKEY")]
+        pubkey: Option<Pubkey>,
+        /// Signer URI to derive the recipient pubkey from, when --pubkey is omitted
+        #[arg(short, long, value_name = "SIGNER_URI")]
+        signer: Option<String>,
+        /// Amount to airdrop, in SOL
+        #[arg(short, long)]
+        amount: f64,
+        /// Commitment level to confirm the airdrop against
+        #[arg(long, value_enum, default_value = "confirmed")]
+        commitment: CommitmentLevelArg,
     },
 }
 
@@ -50,6 +114,24 @@ enum Commands {
 enum Format {
     Base58,
     Bytes,
+    Mnemonic,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum CommitmentLevelArg {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl From<CommitmentLevelArg> for CommitmentConfig {
+    fn from(commitment: CommitmentLevelArg) -> Self {
+        match commitment {
+            CommitmentLevelArg::Processed => CommitmentConfig::processed(),
+            CommitmentLevelArg::Confirmed => CommitmentConfig::confirmed(),
+            CommitmentLevelArg::Finalized => CommitmentConfig::finalized(),
+        }
+    }
 }
 
 impl Cli {
@@ -57,20 +139,42 @@ impl Cli {
         let cli: Cli = Cli::parse();
 
         match &cli.command {
-            Some(Commands::KeypairTransform { transform, path, value }) => {
-                let kp_raw_value = match (path, value) {
-                    (Some(path), None) => read_keypair_file_as_str(path),
-                    (None, Some(value)) => value.to_string(),
-                    _ => return Self::command().print_help().unwrap()
+            Some(Commands::KeypairTransform { transform, path, value, encrypted, mnemonic_passphrase, format }) => {
+                if *transform == Format::Mnemonic {
+                    if path.is_some() || value.is_some() {
+                        panic!("--transform mnemonic generates a fresh keypair; pass neither --path nor --value");
+                    }
+                    let (kp, phrase) = generate_keypair_with_mnemonic(mnemonic_passphrase);
+                    println!("Transformed keypair={:?}", phrase);
+                    println!("Pubkey={}", kp.pubkey());
+                    return;
+                }
+
+                let parse_format = match format {
+                    Some(f) => *f,
+                    None => match transform {
+                        Format::Base58 => Format::Bytes,
+                        Format::Bytes => Format::Base58,
+                        Format::Mnemonic => unreachable!("handled above")
+                    }
                 };
-                let format = match transform {
-                    Format::Base58 => Format::Bytes,
-                    Format::Bytes => Format::Base58
+                let kp = match (path, value) {
+                    (Some(uri), None) => load_keypair_from_uri(uri, &parse_format, *encrypted, mnemonic_passphrase),
+                    (None, Some(value)) => create_keypair(value, &parse_format, mnemonic_passphrase),
+                    _ => return Self::command().print_help().unwrap()
                 };
-                let kp = create_keypair(kp_raw_value.as_str(), &format);
-                print_transform_keypair(&kp, &transform);
+                if *encrypted {
+                    if *transform != Format::Bytes {
+                        panic!("--encrypted is only supported with --transform bytes, to avoid printing an unprotected private key");
+                    }
+                    let passphrase = prompt_passphrase("Enter passphrase to encrypt keypair: ");
+                    let encrypted_kp = encrypt_keypair_bytes(&kp.to_bytes(), &passphrase);
+                    println!("Transformed keypair={}", serde_json::to_string(&encrypted_kp).unwrap());
+                } else {
+                    print_transform_keypair(&kp, &transform);
+                }
             }
-            Some(Commands::TransactionSend { url, transaction, signer, format }) => {
+            Some(Commands::TransactionSend { url, transaction, signer, format, encrypted, sign_only, presigners, commitment, skip_preflight, max_retries }) => {
                 let url = normalize_to_url_if_moniker(url);
                 let client = RpcClient::new(url);
                 println!("RpcClient={:?}", client.url());
@@ -78,24 +182,83 @@ impl Cli {
                 let decoded_tx = base64::decode(transaction).unwrap();
                 let mut tx = bincode::deserialize::<Transaction>(&decoded_tx).unwrap();
                 println!("Decoded tx={:?}", tx);
-                if let Some(signer_path) = signer {
-                    let kp_raw_value = read_keypair_file_as_str(signer_path);
-                    let format = match format {
-                        Some(f) => f,
-                        None => &Format::Bytes
-                    };
-                    let kp = create_keypair(kp_raw_value.as_str(), &format);
-                    tx.partial_sign(&[&kp], tx.message.recent_blockhash);
+
+                for presigner in presigners {
+                    apply_presigner(&mut tx, presigner);
+                }
+
+                let format = match format {
+                    Some(f) => f,
+                    None => &Format::Bytes
+                };
+                let mut wallet_manager = None;
+                let mut seen_pubkeys = HashSet::new();
+                let signers: Vec<Box<dyn Signer>> = signer.iter()
+                    .map(|uri| resolve_signer(uri, &mut wallet_manager, format, *encrypted, ""))
+                    .filter(|signer| seen_pubkeys.insert(signer.pubkey()))
+                    .collect();
+                if !signers.is_empty() {
+                    let signer_refs: Vec<&dyn Signer> = signers.iter().map(|signer| signer.as_ref()).collect();
+                    tx.partial_sign(&signer_refs, tx.message.recent_blockhash);
                     println!("Signed tx={:?}", tx);
                 }
 
+                if *sign_only {
+                    let sign_only_result = SignOnly::from_transaction(&tx);
+                    print_sign_only(&sign_only_result);
+                    return;
+                }
+
+                let commitment_config = CommitmentConfig::from(*commitment);
+                let send_config = RpcSendTransactionConfig {
+                    skip_preflight: *skip_preflight,
+                    preflight_commitment: Some(commitment_config.commitment),
+                    max_retries: *max_retries,
+                    ..RpcSendTransactionConfig::default()
+                };
+
                 println!("Send tx to blockchain");
-                let tx_result = client.send_and_confirm_transaction(&tx);
+                let tx_result = client.send_and_confirm_transaction_with_spinner_and_config(&tx, commitment_config, send_config);
                 match tx_result {
                     Ok(signature) => println!("Tx executed SUCCESS, txSignature={:?}", signature),
                     Err(error) => println!("Tx executed FAILED, error={:?}", error)
                 }
             }
+            Some(Commands::Airdrop { url, pubkey, signer, amount, commitment }) => {
+                let url = normalize_to_url_if_moniker(url);
+                let client = RpcClient::new(url);
+                println!("RpcClient={:?}", client.url());
+
+                let recipient = match (pubkey, signer) {
+                    (Some(pubkey), _) => *pubkey,
+                    (None, Some(uri)) => {
+                        let mut wallet_manager = None;
+                        resolve_signer(uri, &mut wallet_manager, &Format::Bytes, false, "").pubkey()
+                    }
+                    (None, None) => return Self::command().print_help().unwrap()
+                };
+
+                let lamports = sol_to_lamports(*amount);
+                let signature = match client.request_airdrop(&recipient, lamports) {
+                    Ok(signature) => signature,
+                    Err(error) => return println!("Airdrop FAILED, error={:?}", error)
+                };
+                println!("Airdrop txSignature={:?}", signature);
+
+                let commitment_config = CommitmentConfig::from(*commitment);
+                let recent_blockhash = match client.get_latest_blockhash() {
+                    Ok(recent_blockhash) => recent_blockhash,
+                    Err(error) => return println!("Airdrop FAILED, error={:?}", error)
+                };
+                if let Err(error) = client.confirm_transaction_with_spinner(&signature, &recent_blockhash, commitment_config) {
+                    return println!("Airdrop FAILED, error={:?}", error);
+                }
+
+                match client.get_balance(&recipient) {
+                    Ok(balance) => println!("Balance={} lamports", balance),
+                    Err(error) => println!("Airdrop FAILED, error={:?}", error)
+                }
+            }
             None => Self::command().print_help().unwrap(),
         }
     }
@@ -113,7 +276,7 @@ fn read_keypair_file_as_str(path: &PathBuf) -> String {
     return result;
 }
 
-fn create_keypair(kp_value: &str, format: &Format) -> Keypair {
+fn create_keypair(kp_value: &str, format: &Format, mnemonic_passphrase: &str) -> Keypair {
     match format {
         Format::Base58 => Keypair::from_base58_string(&kp_value),
         Format::Bytes => {
@@ -121,12 +284,210 @@ fn create_keypair(kp_value: &str, format: &Format) -> Keypair {
             read_keypair(&mut kp_value)
                 .expect("could not create keypair from value")
         }
+        Format::Mnemonic => derive_keypair_from_mnemonic(kp_value.trim(), mnemonic_passphrase)
+    }
+}
+
+fn load_keypair_from_uri(uri: &str, format: &Format, encrypted: bool, mnemonic_passphrase: &str) -> Keypair {
+    if let Some(path) = uri.strip_prefix("file://") {
+        let kp_raw_value = read_keypair_file_as_str(&PathBuf::from(path));
+        if encrypted {
+            read_encrypted_keypair(kp_raw_value.as_str())
+        } else {
+            create_keypair(kp_raw_value.as_str(), format, mnemonic_passphrase)
+        }
+    } else if uri == "prompt://" {
+        read_keypair_from_prompt(mnemonic_passphrase)
+    } else {
+        panic!("unsupported signer URI for this operation: {}", uri)
+    }
+}
+
+fn resolve_signer(uri: &str, wallet_manager: &mut Option<Arc<RemoteWalletManager>>, format: &Format, encrypted: bool, mnemonic_passphrase: &str) -> Box<dyn Signer> {
+    if let Some(path) = uri.strip_prefix("file://") {
+        Box::new(load_keypair_from_uri(&format!("file://{}", path), format, encrypted, mnemonic_passphrase))
+    } else if let Some(locator) = uri.strip_prefix("usb://") {
+        let manager = wallet_manager.get_or_insert_with(|| {
+            maybe_wallet_manager()
+                .expect("could not query hardware wallets")
+                .expect("no hardware wallet found")
+        });
+        resolve_remote_wallet_signer(locator, manager)
+    } else if uri == "prompt://" {
+        Box::new(read_keypair_from_prompt(mnemonic_passphrase))
+    } else {
+        panic!("unsupported signer URI scheme: {}", uri)
+    }
+}
+
+fn resolve_remote_wallet_signer(locator: &str, wallet_manager: &Arc<RemoteWalletManager>) -> Box<dyn Signer> {
+    let (manufacturer, query) = locator.split_once('?').unwrap_or((locator, ""));
+    let derivation_path = query.strip_prefix("key=")
+        .map(|key| DerivationPath::from_key_str(key).expect("invalid derivation path in --signer URI"))
+        .unwrap_or_default();
+    let wallet_locator = RemoteWalletLocator::new_from_path(&format!("usb://{}", manufacturer))
+        .expect("invalid hardware wallet locator in --signer URI");
+    let keypair = generate_remote_keypair(wallet_locator, derivation_path, wallet_manager, false, "signer")
+        .expect("could not connect to hardware wallet");
+    Box::new(keypair)
+}
+
+fn read_keypair_from_prompt(mnemonic_passphrase: &str) -> Keypair {
+    println!("Enter seed phrase:");
+    let mut phrase = String::new();
+    std::io::stdin().read_line(&mut phrase).expect("could not read seed phrase");
+    derive_keypair_from_mnemonic(phrase.trim(), mnemonic_passphrase)
+}
+
+fn derive_keypair_from_mnemonic(phrase: &str, passphrase: &str) -> Keypair {
+    let mnemonic = bip39::Mnemonic::parse(phrase)
+        .expect("invalid BIP39 seed phrase");
+    let seed = mnemonic.to_seed(passphrase);
+    solana_sdk::signer::keypair::keypair_from_seed(&seed[..32])
+        .expect("could not derive keypair from seed phrase")
+}
+
+fn generate_keypair_with_mnemonic(passphrase: &str) -> (Keypair, String) {
+    let mut entropy = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut entropy);
+    let mnemonic = bip39::Mnemonic::from_entropy(&entropy)
+        .expect("could not generate mnemonic from entropy");
+    let phrase = mnemonic.to_string();
+    let kp = derive_keypair_from_mnemonic(phrase.as_str(), passphrase);
+    (kp, phrase)
+}
+
+const PBKDF2_ROUNDS: u32 = 100_000;
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedKeypair {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn prompt_passphrase(prompt: &str) -> String {
+    rpassword::prompt_password(prompt).expect("could not read passphrase")
+}
+
+fn derive_encryption_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+    key
+}
+
+fn encrypt_keypair_bytes(keypair_bytes: &[u8], passphrase: &str) -> EncryptedKeypair {
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let mut nonce_bytes = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+
+    let key = derive_encryption_key(passphrase, &salt);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+    let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce_bytes), keypair_bytes)
+        .expect("could not encrypt keypair");
+
+    EncryptedKeypair {
+        salt: base64::encode(salt),
+        nonce: base64::encode(nonce_bytes),
+        ciphertext: base64::encode(ciphertext),
+    }
+}
+
+fn decrypt_keypair_bytes(encrypted: &EncryptedKeypair, passphrase: &str) -> Result<Vec<u8>, chacha20poly1305::aead::Error> {
+    let salt = base64::decode(&encrypted.salt).expect("invalid salt encoding");
+    let nonce_bytes = base64::decode(&encrypted.nonce).expect("invalid nonce encoding");
+    let ciphertext = base64::decode(&encrypted.ciphertext).expect("invalid ciphertext encoding");
+
+    let key = derive_encryption_key(passphrase, &salt);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+    cipher.decrypt(Nonce::from_slice(nonce_bytes.as_slice()), ciphertext.as_ref())
+}
+
+fn read_encrypted_keypair(json: &str) -> Keypair {
+    let encrypted = match serde_json::from_str::<EncryptedKeypair>(json) {
+        Ok(encrypted) => encrypted,
+        Err(_) => return create_keypair(json, &Format::Bytes, "")
+    };
+    let passphrase = prompt_passphrase("Enter passphrase: ");
+    match decrypt_keypair_bytes(&encrypted, &passphrase) {
+        Ok(bytes) => Keypair::from_bytes(&bytes).expect("could not construct keypair from decrypted bytes"),
+        Err(_) => {
+            println!("Could not decrypt keypair, wrong passphrase?");
+            std::process::exit(1);
+        }
     }
 }
 
 fn print_transform_keypair(keypair: &Keypair, transform: &Format) {
     match transform {
         Format::Base58 => println!("Transformed keypair={:?}", keypair.to_base58_string()),
-        Format::Bytes => println!("Transformed keypair={:?}", keypair.to_bytes())
+        Format::Bytes => println!("Transformed keypair={:?}", keypair.to_bytes()),
+        Format::Mnemonic => unreachable!("mnemonic output is printed by the --transform mnemonic branch of Cli::start")
     };
+}
+
+struct SignOnly {
+    recent_blockhash: Hash,
+    present_signers: Vec<(Pubkey, Signature)>,
+    absent_signers: Vec<Pubkey>,
+    bad_signers: Vec<Pubkey>,
+}
+
+impl SignOnly {
+    fn from_transaction(tx: &Transaction) -> Self {
+        let num_required_signatures = tx.message.header.num_required_signatures as usize;
+        let verify_results = tx.verify_with_results();
+
+        let mut present_signers = Vec::new();
+        let mut absent_signers = Vec::new();
+        let mut bad_signers = Vec::new();
+
+        for i in 0..num_required_signatures {
+            let pubkey = tx.message.account_keys[i];
+            let signature = tx.signatures[i];
+            if signature == Signature::default() {
+                absent_signers.push(pubkey);
+            } else if verify_results[i] {
+                present_signers.push((pubkey, signature));
+            } else {
+                bad_signers.push(pubkey);
+            }
+        }
+
+        SignOnly {
+            recent_blockhash: tx.message.recent_blockhash,
+            present_signers,
+            absent_signers,
+            bad_signers,
+        }
+    }
+}
+
+fn print_sign_only(sign_only: &SignOnly) {
+    println!("Blockhash={:?}", sign_only.recent_blockhash);
+    for (pubkey, signature) in &sign_only.present_signers {
+        println!("{}={}", pubkey, signature);
+    }
+    if !sign_only.absent_signers.is_empty() {
+        println!("Absent Signers={:?}", sign_only.absent_signers);
+    }
+    if !sign_only.bad_signers.is_empty() {
+        println!("Bad Signers={:?}", sign_only.bad_signers);
+    }
+}
+
+fn apply_presigner(tx: &mut Transaction, presigner: &str) {
+    let (pubkey_str, signature_str) = presigner.split_once('=')
+        .expect("invalid --presigner value, expected PUBKEY=BASE58_SIGNATURE");
+    let pubkey = Pubkey::from_str(pubkey_str)
+        .expect("invalid pubkey in --presigner value");
+    let signature = Signature::from_str(signature_str)
+        .expect("invalid signature in --presigner value");
+    let index = tx.message.account_keys.iter().position(|key| key == &pubkey)
+        .expect("presigner pubkey not found in account_keys");
+    if index >= tx.message.header.num_required_signatures as usize {
+        panic!("presigner pubkey {} is not a required signer", pubkey);
+    }
+    tx.signatures[index] = signature;
 }
\ No newline at end of file